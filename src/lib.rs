@@ -3,8 +3,6 @@
 
 // Externs
 extern crate sysconf;
-extern crate csv;
-extern crate rustc_serialize;
 
 
 // Imports
@@ -25,4 +23,19 @@ pub enum Error {
     RuntimeError(String),
     IoError(io::Error),
     ParseError(ParseIntError),
+    /// A `/proc` file didn't match the format `procure` expected, e.g. a
+    /// `stat` line missing its `(comm)` field.
+    MalformedStat(String),
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::IoError(err)
+    }
+}
+
+impl From<ParseIntError> for Error {
+    fn from(err: ParseIntError) -> Error {
+        Error::ParseError(err)
+    }
 }
\ No newline at end of file
@@ -1,78 +1,136 @@
 //! Process Metrics
+use std::collections::HashMap;
+use std::fmt;
 use std::fs;
 use std::fs::File;
 use std::io::Read;
+use std::iter;
 use std::iter::Iterator;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use csv::Reader;
+use sysconf::raw::{sysconf, SysconfVariable};
 
-use rustc_serialize::{Decoder, Decodable};
+use super::{Error, Result};
 
 #[derive(Debug)]
 pub struct Process {
     pub pid: i32,
+    /// `argv[0]` from `/proc/[pid]/cmdline`, kept for compatibility; see
+    /// `cmdline` for the full argument list.
     pub command: String,
+    /// The process's argument list, read from the NUL-separated
+    /// `/proc/[pid]/cmdline` and split on `\0`. Empty for kernel threads.
+    pub cmdline: Vec<String>,
+    /// The real binary backing this process, resolved from the
+    /// `/proc/[pid]/exe` symlink. `None` for kernel threads (no `exe` to
+    /// resolve) or processes we don't have permission to inspect. Useful
+    /// for telling the real executable apart from a spoofed `argv[0]`.
+    pub exe: Option<PathBuf>,
     pub start_time: u64,
     pub rss: i32,
     pub vsz: u64,
     pub cpu_time: u64,
+    /// Percentage of a single core consumed since the last sample. Can
+    /// exceed 100% on multi-core hosts; see `cpu_percent_normalized` for a
+    /// value scaled to the number of online CPUs.
     pub cpu_percent: f32,
+    /// `cpu_percent` divided by the number of online CPUs, i.e. the share
+    /// of the whole host's CPU capacity. The value is not clamped.
+    pub cpu_percent_normalized: f32,
+    pub status: ProcessStatus,
+    pub disk_usage: DiskUsage,
 }
 
-#[derive(RustcDecodable)]
+/// Per-process storage I/O, read from `/proc/[pid]/io`. `read_bytes` and
+/// `written_bytes` are the delta over the most recent sampling interval
+/// (see `ProcessSampler`); `total_read_bytes`/`total_written_bytes` are
+/// the cumulative counters reported by the kernel.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiskUsage {
+    pub read_bytes: u64,
+    pub written_bytes: u64,
+    pub total_read_bytes: u64,
+    pub total_written_bytes: u64,
+}
+
+impl Process {
+    /// Enumerates this process's threads via `/proc/[pid]/task`.
+    pub fn threads(&self) -> Threads {
+        threads_from_path("/proc", self.pid)
+    }
+}
+
+/// The run state reported in the `state` field of `/proc/[pid]/stat`, as
+/// documented in `proc(5)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessStatus {
+    Run,
+    Sleep,
+    Idle,
+    UninterruptibleDiskSleep,
+    Zombie,
+    Stop,
+    Tracing,
+    Dead,
+    Wakekill,
+    Waking,
+    Parked,
+    /// A state code not recognized by this crate.
+    Unknown(char),
+}
+
+impl From<char> for ProcessStatus {
+    fn from(c: char) -> ProcessStatus {
+        match c {
+            'R' => ProcessStatus::Run,
+            'S' => ProcessStatus::Sleep,
+            'I' => ProcessStatus::Idle,
+            'D' => ProcessStatus::UninterruptibleDiskSleep,
+            'Z' => ProcessStatus::Zombie,
+            'T' => ProcessStatus::Stop,
+            't' => ProcessStatus::Tracing,
+            'X' | 'x' => ProcessStatus::Dead,
+            'K' => ProcessStatus::Wakekill,
+            'W' => ProcessStatus::Waking,
+            'P' => ProcessStatus::Parked,
+            other => ProcessStatus::Unknown(other),
+        }
+    }
+}
+
+impl fmt::Display for ProcessStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProcessStatus::Run => write!(f, "running"),
+            ProcessStatus::Sleep => write!(f, "sleeping"),
+            ProcessStatus::Idle => write!(f, "idle"),
+            ProcessStatus::UninterruptibleDiskSleep => write!(f, "uninterruptible disk sleep"),
+            ProcessStatus::Zombie => write!(f, "zombie"),
+            ProcessStatus::Stop => write!(f, "stopped"),
+            ProcessStatus::Tracing => write!(f, "tracing stop"),
+            ProcessStatus::Dead => write!(f, "dead"),
+            ProcessStatus::Wakekill => write!(f, "wakekill"),
+            ProcessStatus::Waking => write!(f, "waking"),
+            ProcessStatus::Parked => write!(f, "parked"),
+            ProcessStatus::Unknown(c) => write!(f, "unknown ({})", c),
+        }
+    }
+}
+
+// Only the fields `Process`/`Thread` actually surface, plus `pid` and
+// `comm`, which only the parser tests below read. `proc(5)` documents ~50
+// fields on this line; the rest are not parsed.
 struct ProcessStat {
     pid: i32,
     comm: String,
     state: char,
-    ppid: i32,
-    pgrp: i32,
-    session: i32,
-    tty_nr: i32,
-    tpgid: i32,
-    flags: u32,
-    minflt: u64,
-    cminflt: u64,
-    majflt: u64,
-    cmajflt: u64,
     utime: u64,
     stime: u64,
-    cutime: i64,
-    cstime: i64,
-    priority: i64,
-    nice: i64,
-    num_threads: i64,
-    itrealvalue: i64,
     starttime: u64,
     vsize: u64,
     rss: i32,
-    rsslim: u64,
-    startcode: u64,
-    endcode: u64,
-    startstack: u64,
-    kstkesp: u64,
-    kstkeip: u64,
-    signal: u64,
-    blocked: u64,
-    sigignore: u64,
-    sigcatch: u64,
-    wchan: u64,
-    nswap: u64,
-    cnswap: u64,
-    exit_signal: i32,
-    processor: i32,
-    rt_priority: u32,
-    policy: u32,
-    delayacct_blkio_ticks: u64, // llu?
-    guest_time: u64,
-    cguesttime: i64,
-    start_data: u64,
-    end_data: u64,
-    start_brk: u64,
-    arg_start: u64,
-    arg_end: u64,
-    env_start: u64,
-    env_end: u64,
-    exit_code: u64,
 }
 
 pub struct Processes {
@@ -87,6 +145,21 @@ impl Iterator for Processes {
     }
 }
 
+/// Like [`Processes`](struct.Processes.html), but surfaces each PID's
+/// `Error` instead of silently dropping it, so callers can log why a
+/// process went missing.
+pub struct ProcessResults {
+    iter: Box<Iterator<Item = Result<Process>>>,
+}
+
+impl Iterator for ProcessResults {
+    type Item = Result<Process>;
+
+    fn next(&mut self) -> Option<Result<Process>> {
+        self.iter.next()
+    }
+}
+
 pub struct Pids {
     iter: Box<Iterator<Item = i32>>,
 }
@@ -99,8 +172,35 @@ impl Iterator for Pids {
     }
 }
 
+/// A single thread (task) belonging to a `Process`.
+#[derive(Debug)]
+pub struct Thread {
+    pub tid: i32,
+    pub cpu_time: u64,
+    pub status: ProcessStatus,
+}
+
+pub struct Threads {
+    iter: Box<Iterator<Item = Thread>>,
+}
+
+impl Iterator for Threads {
+    type Item = Thread;
+
+    fn next(&mut self) -> Option<Thread> {
+        self.iter.next()
+    }
+}
+
+// A `/proc` directory that's unreadable (e.g. gone, or `/proc` itself not
+// mounted) yields an empty `Pids` rather than panicking; that mirrors how
+// individual entries below it are already handled.
 fn pids_from_path(proc_path: &str) -> Pids {
-    let iter = fs::read_dir(proc_path).unwrap()
+    let entries = match fs::read_dir(proc_path) {
+        Ok(entries) => entries,
+        Err(_) => return Pids { iter: Box::new(iter::empty()) },
+    };
+    let iter = entries
         // Process directories might have gone away since
         // the directory was read. It's fine to ignore those.
         .filter_map(|entry| entry.ok())
@@ -113,41 +213,256 @@ fn pids_from_path(proc_path: &str) -> Pids {
 }
 
 fn processes_from_path(proc_path: &str) -> Processes {
+    let processes: Vec<Result<Process>> = processes_from_path_results(proc_path).collect();
+    Processes { iter: Box::new(processes.into_iter().filter_map(|p| p.ok()).into_iter()) }
+}
+
+fn processes_from_path_results(proc_path: &str) -> ProcessResults {
     let pids = pids_from_path(proc_path);
-    let processes: Vec<Result<Process, &'static str>> =
-        pids.map(|pid| process_from_path(proc_path, pid))
+    let mut buf = Vec::new();
+    let processes: Vec<Result<Process>> =
+        pids.map(|pid| process_from_path(proc_path, pid, &mut buf))
             .collect();
-    Processes { iter: Box::new(processes.into_iter().filter_map(|p| p.ok()).into_iter()) }
+    ProcessResults { iter: Box::new(processes.into_iter()) }
+}
+
+fn thread_from_path(proc_path: &str,
+                     pid: i32,
+                     tid: i32,
+                     buf: &mut Vec<u8>)
+                     -> Result<Thread> {
+    let stat = read_stat_file(&format!("{}/{}/task/{}/stat", proc_path, pid, tid), buf)?;
+    Ok(Thread {
+        tid: tid,
+        cpu_time: stat.utime + stat.stime,
+        status: ProcessStatus::from(stat.state),
+    })
+}
+
+// Mirrors `pids_from_path`/`processes_from_path`: TID directories that
+// disappear mid-scan, or whose stat file fails to parse, are dropped
+// rather than failing the whole enumeration.
+fn threads_from_path(proc_path: &str, pid: i32) -> Threads {
+    let tids = pids_from_path(&format!("{}/{}/task", proc_path, pid));
+    let mut buf = Vec::new();
+    let threads: Vec<Result<Thread>> =
+        tids.map(|tid| thread_from_path(proc_path, pid, tid, &mut buf))
+            .collect();
+    Threads { iter: Box::new(threads.into_iter().filter_map(|t| t.ok()).into_iter()) }
 }
 
-fn read_stat_file(path: &str) -> ProcessStat {
-    Reader::from_file(path)
-        .expect("Failed to open file")
-        .has_headers(false)
-        .delimiter(' ' as u8)
-        .decode()
-        .filter_map(|stat| stat.ok())
-        .next()
-        .unwrap()
+fn read_stat_file(path: &str, buf: &mut Vec<u8>) -> Result<ProcessStat> {
+    read_into_buffer(path, buf)?;
+    parse_stat(&String::from_utf8_lossy(buf))
 }
 
-fn process_from_path(proc_path: &str, pid: i32) -> Result<Process, &'static str> {
-    // Gather the process data present in "`path`/`pid`".
-    let mut command = String::new();
-    let mut f = File::open(&format!("{}/{}/cmdline", proc_path, pid)).expect("Failed to open path");
-    f.read_to_string(&mut command).expect("Failed to read file");
-    let stat = read_stat_file(&format!("{}/{}/stat", proc_path, pid));
+// Clears and refills `buf` with the full contents of `path`, rather than
+// allocating a fresh buffer per file read during a scan.
+fn read_into_buffer(path: &str, buf: &mut Vec<u8>) -> Result<()> {
+    buf.clear();
+    let mut f = File::open(path)?;
+    f.read_to_end(buf)?;
+    Ok(())
+}
+
+// `/proc/[pid]/cmdline` is argv joined with NUL separators plus a trailing
+// NUL, rather than a single human-readable string. Splitting on it leaves
+// an empty element from that trailing NUL, so drop it; an empty `cmdline`
+// (e.g. a kernel thread) parses to an empty `Vec`.
+fn parse_cmdline(buf: &[u8]) -> Vec<String> {
+    let contents = String::from_utf8_lossy(buf);
+    let mut argv: Vec<String> = contents.split('\0').map(|s| s.to_string()).collect();
+    if argv.last().map_or(false, |arg| arg.is_empty()) {
+        argv.pop();
+    }
+    argv
+}
+
+// `comm`, the second field, is wrapped in parentheses and can itself
+// contain spaces and parentheses (e.g. a daemon renamed to "foo bar", or
+// the literal kernel thread name "(sd-pam)"). Splitting the whole line on
+// whitespace shifts every field after it, so instead we find the first
+// '(' and the *last* ')' to bound `comm` and split everything else
+// positionally.
+fn parse_stat(line: &str) -> Result<ProcessStat> {
+    let line = line.trim_end_matches('\n');
+    let open = line.find('(')
+        .ok_or_else(|| Error::MalformedStat("missing '(' around comm".to_string()))?;
+    let close = line.rfind(')')
+        .ok_or_else(|| Error::MalformedStat("missing ')' around comm".to_string()))?;
+    if close <= open {
+        return Err(Error::MalformedStat("')' around comm precedes '('".to_string()));
+    }
+
+    let pid = line[..open].trim().parse()?;
+    let comm = line[open + 1..close].to_string();
+    let rest: Vec<&str> = line[close + 1..].split_whitespace().collect();
+    // Field positions per `proc(5)`, counted after `comm`: 0 = state,
+    // 11 = utime, 12 = stime, 19 = starttime, 20 = vsize, 21 = rss.
+    if rest.len() < 22 {
+        return Err(Error::MalformedStat(format!("expected at least 22 fields after comm, found {}",
+                                                  rest.len())));
+    }
+
+    Ok(ProcessStat {
+        pid: pid,
+        comm: comm,
+        state: rest[0].chars().next()
+            .ok_or_else(|| Error::MalformedStat("missing state field".to_string()))?,
+        utime: rest[11].parse()?,
+        stime: rest[12].parse()?,
+        starttime: rest[19].parse()?,
+        vsize: rest[20].parse()?,
+        rss: rest[21].parse()?,
+    })
+}
+
+fn process_from_path(proc_path: &str,
+                      pid: i32,
+                      buf: &mut Vec<u8>)
+                      -> Result<Process> {
+    // Gather the process data present in "`path`/`pid`", reusing `buf` for
+    // each file read instead of allocating a fresh one.
+    read_into_buffer(&format!("{}/{}/cmdline", proc_path, pid), buf)?;
+    let cmdline = parse_cmdline(buf);
+    let command = cmdline.get(0).cloned().unwrap_or_default();
+    let exe = fs::read_link(format!("{}/{}/exe", proc_path, pid)).ok();
+
+    let stat = read_stat_file(&format!("{}/{}/stat", proc_path, pid), buf)?;
+    let (total_read_bytes, total_written_bytes) = read_io_totals(proc_path, pid, buf);
     Ok(Process {
         pid: pid,
         command: command,
+        cmdline: cmdline,
+        exe: exe,
         start_time: stat.starttime,
         rss: stat.rss,
         vsz: stat.vsize,
         cpu_time: stat.utime + stat.stime,
         cpu_percent: 0f32,
+        cpu_percent_normalized: 0f32,
+        status: ProcessStatus::from(stat.state),
+        disk_usage: DiskUsage {
+            read_bytes: 0,
+            written_bytes: 0,
+            total_read_bytes: total_read_bytes,
+            total_written_bytes: total_written_bytes,
+        },
     })
 }
 
+// Reads the cumulative `read_bytes`/`write_bytes` counters from
+// `/proc/[pid]/io`. That file is only readable by the process's owner, so
+// for processes we don't own this returns zeros rather than failing the
+// whole collection.
+fn read_io_totals(proc_path: &str, pid: i32, buf: &mut Vec<u8>) -> (u64, u64) {
+    buf.clear();
+    let read = File::open(&format!("{}/{}/io", proc_path, pid)).and_then(|mut f| f.read_to_end(buf));
+    if read.is_err() {
+        return (0, 0);
+    }
+
+    let contents = String::from_utf8_lossy(buf);
+    let mut read_bytes = 0u64;
+    let mut written_bytes = 0u64;
+    for line in contents.lines() {
+        let mut parts = line.splitn(2, ':');
+        let key = parts.next().unwrap_or("").trim();
+        let value = parts.next().unwrap_or("").trim();
+        match key {
+            "read_bytes" => read_bytes = value.parse().unwrap_or(0),
+            "write_bytes" => written_bytes = value.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+    (read_bytes, written_bytes)
+}
+
+// Deltas a monotonic counter between two samples, yielding `0` instead of
+// underflowing if the counter ever goes backwards (e.g. wraparound).
+fn saturating_delta(before: u64, after: u64) -> u64 {
+    if after >= before { after - before } else { 0 }
+}
+
+/// Takes two `/proc` snapshots separated by an interval and fills in
+/// `cpu_percent`/`cpu_percent_normalized` from the CPU time delta between
+/// them.
+pub struct ProcessSampler {
+    proc_path: String,
+    clk_tck: isize,
+    num_cpus: isize,
+}
+
+impl ProcessSampler {
+    pub fn new() -> ProcessSampler {
+        ProcessSampler::from_path("/proc")
+    }
+
+    fn from_path(proc_path: &str) -> ProcessSampler {
+        ProcessSampler {
+            proc_path: proc_path.to_string(),
+            clk_tck: sysconf(SysconfVariable::ScClkTck).unwrap_or(100),
+            num_cpus: sysconf(SysconfVariable::ScNprocessorsOnln).unwrap_or(1),
+        }
+    }
+
+    /// Samples `/proc` twice, `interval` apart, and returns each process
+    /// still alive at the end of the window with its CPU and disk usage
+    /// computed from the deltas. Processes that exited between samples are
+    /// dropped; processes that appeared since the first sample are
+    /// reported with zeroed deltas, since there's no baseline to diff
+    /// against.
+    pub fn sample(&self, interval: Duration) -> Vec<Process> {
+        let before: HashMap<i32, (u64, u64, u64)> =
+            processes_from_path(&self.proc_path)
+                .map(|p| (p.pid, (p.cpu_time, p.disk_usage.total_read_bytes, p.disk_usage.total_written_bytes)))
+                .collect();
+
+        let start = Instant::now();
+        thread::sleep(interval);
+        let elapsed_secs = duration_secs(Instant::now().duration_since(start));
+
+        processes_from_path(&self.proc_path)
+            .map(|mut process| {
+                if let Some(&(prev_cpu_time, prev_read_bytes, prev_written_bytes)) =
+                    before.get(&process.pid) {
+                    let (percent, normalized) = cpu_percent(prev_cpu_time,
+                                                             process.cpu_time,
+                                                             elapsed_secs,
+                                                             self.clk_tck,
+                                                             self.num_cpus);
+                    process.cpu_percent = percent;
+                    process.cpu_percent_normalized = normalized;
+                    process.disk_usage.read_bytes =
+                        saturating_delta(prev_read_bytes, process.disk_usage.total_read_bytes);
+                    process.disk_usage.written_bytes =
+                        saturating_delta(prev_written_bytes, process.disk_usage.total_written_bytes);
+                }
+                process
+            })
+            .collect()
+    }
+}
+
+fn duration_secs(duration: Duration) -> f32 {
+    duration.as_secs() as f32 + (duration.subsec_nanos() as f32 / 1_000_000_000f32)
+}
+
+// Computes (cpu_percent, cpu_percent_normalized) from a CPU time delta.
+// Guards against a wraparound in `cpu_time` (after < before) and against
+// `elapsed_secs == 0`, both of which would otherwise divide by zero or
+// underflow.
+fn cpu_percent(before: u64, after: u64, elapsed_secs: f32, clk_tck: isize, num_cpus: isize) -> (f32, f32) {
+    if elapsed_secs <= 0f32 || after < before || clk_tck <= 0 {
+        return (0f32, 0f32);
+    }
+    let ticks_delta = after - before;
+    let cpu_secs = ticks_delta as f32 / clk_tck as f32;
+    let percent = (cpu_secs / elapsed_secs) * 100f32;
+    let normalized = if num_cpus > 0 { percent / num_cpus as f32 } else { percent };
+    (percent, normalized)
+}
+
 // Public interface
 
 pub fn pids() -> Pids {
@@ -158,6 +473,13 @@ pub fn processes() -> Processes {
     processes_from_path("/proc")
 }
 
+/// Like [`processes`](fn.processes.html), but yields a `Result` per PID
+/// instead of silently dropping the ones that failed to read, so callers
+/// can log why a process went missing.
+pub fn processes_results() -> ProcessResults {
+    processes_from_path_results("/proc")
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -182,9 +504,117 @@ mod tests {
 
     #[test]
     fn test_process_from_path() {
-        let process = super::process_from_path("testdata/proc", 1);
+        let mut buf = Vec::new();
+        let process = super::process_from_path("testdata/proc", 1, &mut buf);
         // assert!(process.name == "init");
         // assert!(process.VmRSS == 2164);
         assert!(process.unwrap().command == "/sbin/init");
     }
+
+    const STAT_TAIL: &'static str = "24064 24126 24064 34816 24126 4194304 286 0 0 0 3 1 0 0 \
+        20 0 1 0 123456 4792320 322 18446744073709551615 4194304 4196996 140735555711744 \
+        140735555711040 140556250439920 0 0 0 0 0 0 0 17 2 0 0 0 0 0 4198296 4198488 8626176 \
+        140735555718296 140735555718316 140735555718316 140735555718326 0";
+
+    #[test]
+    fn test_parse_stat_with_plain_comm() {
+        let line = format!("1 (init) S {}", STAT_TAIL);
+        let stat = super::parse_stat(&line).unwrap();
+        assert_eq!(stat.pid, 1);
+        assert_eq!(stat.comm, "init");
+        assert_eq!(stat.state, 'S');
+    }
+
+    #[test]
+    fn test_parse_stat_with_spaces_in_comm() {
+        let line = format!("24126 (foo bar) S {}", STAT_TAIL);
+        let stat = super::parse_stat(&line).unwrap();
+        assert_eq!(stat.pid, 24126);
+        assert_eq!(stat.comm, "foo bar");
+        assert_eq!(stat.state, 'S');
+    }
+
+    #[test]
+    fn test_parse_stat_with_parens_in_comm() {
+        let line = format!("16018 ((sd-pam)) S {}", STAT_TAIL);
+        let stat = super::parse_stat(&line).unwrap();
+        assert_eq!(stat.pid, 16018);
+        assert_eq!(stat.comm, "(sd-pam)");
+    }
+
+    #[test]
+    fn test_process_status_from_char() {
+        assert_eq!(ProcessStatus::from('R'), ProcessStatus::Run);
+        assert_eq!(ProcessStatus::from('Z'), ProcessStatus::Zombie);
+        assert_eq!(ProcessStatus::from('x'), ProcessStatus::Dead);
+        assert_eq!(ProcessStatus::from('?'), ProcessStatus::Unknown('?'));
+    }
+
+    #[test]
+    fn test_process_status_display() {
+        assert_eq!(ProcessStatus::Sleep.to_string(), "sleeping");
+        assert_eq!(ProcessStatus::Unknown('?').to_string(), "unknown (?)");
+    }
+
+    #[test]
+    fn test_cpu_percent_basic() {
+        // 100 ticks/sec clk_tck, 100 ticks of CPU time over a 2-second
+        // window on a 4-core host: 1 CPU-second / 2 seconds = 50%, scaled
+        // down to 12.5% once normalized across the 4 cores.
+        let (percent, normalized) = super::cpu_percent(1000, 1100, 2f32, 100, 4);
+        assert!((percent - 50f32).abs() < 0.001);
+        assert!((normalized - 12.5f32).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_cpu_percent_wraparound_yields_zero() {
+        // `after < before` happens if the counter wraps between samples;
+        // treat it as no usage rather than underflowing.
+        let (percent, normalized) = super::cpu_percent(1100, 1000, 2f32, 100, 4);
+        assert_eq!(percent, 0f32);
+        assert_eq!(normalized, 0f32);
+    }
+
+    #[test]
+    fn test_cpu_percent_zero_elapsed_yields_zero() {
+        let (percent, normalized) = super::cpu_percent(1000, 1100, 0f32, 100, 4);
+        assert_eq!(percent, 0f32);
+        assert_eq!(normalized, 0f32);
+    }
+
+    #[test]
+    fn test_read_io_totals_missing_file_yields_zeros() {
+        // `/proc/[pid]/io` is owner-only, so a process we can't read (or
+        // one that's already gone) should degrade to zeros rather than
+        // failing the whole collection.
+        let mut buf = Vec::new();
+        let (read_bytes, written_bytes) = super::read_io_totals("testdata/proc", 999999, &mut buf);
+        assert_eq!(read_bytes, 0);
+        assert_eq!(written_bytes, 0);
+    }
+
+    #[test]
+    fn test_threads_from_path_missing_pid_yields_empty() {
+        // Mirrors `pids_from_path`/`processes_from_path`: a PID whose
+        // `task` directory can't be read drops out rather than panicking.
+        let threads: Vec<Thread> = super::threads_from_path("testdata/proc", 999999).collect();
+        assert!(threads.is_empty());
+    }
+
+    #[test]
+    fn test_parse_cmdline_drops_trailing_nul() {
+        assert_eq!(super::parse_cmdline(b"a\0b\0"), vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_cmdline_single_arg() {
+        assert_eq!(super::parse_cmdline(b"onlyarg\0"), vec!["onlyarg".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_cmdline_empty_is_kernel_thread() {
+        // Kernel threads have an empty `/proc/[pid]/cmdline`.
+        let argv: Vec<String> = super::parse_cmdline(b"");
+        assert!(argv.is_empty());
+    }
 }